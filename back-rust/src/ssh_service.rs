@@ -1,13 +1,52 @@
+use base64::Engine;
 use crate::types::*;
+use serde_json::json;
 use socketioxide::extract::SocketRef;
 use ssh2::Session;
-use std::collections::HashMap;
-use std::io::{Read, Write};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
 use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::task;
 use tracing::{error, info};
 
+/// How much recent terminal output is kept per session so a reattaching
+/// socket can replay it instead of seeing a blank screen.
+const OUTPUT_BUFFER_CAP: usize = 64 * 1024;
+/// How long a session may sit with no attached socket before it is reaped.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+/// How often the idle reaper scans for orphaned sessions.
+const IDLE_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Resolves `~/.ssh/known_hosts` for host-key verification.
+fn dirs_home_known_hosts() -> std::path::PathBuf {
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .unwrap_or_else(|_| ".".to_string());
+    std::path::PathBuf::from(home).join(".ssh").join("known_hosts")
+}
+
+/// Parameters for opening a new SSH session, sent by the frontend on the
+/// `ssh-connect` event. `connect` authenticates with the first of
+/// `use_agent`, `password`, `private_key`, `use_keyboard_interactive` that is
+/// set, in that order, and fails if none of them is. If that method fails,
+/// the later ones are not tried.
+#[derive(serde::Deserialize)]
+pub struct SshConnectionConfig {
+    pub server_id: String,
+    pub ip: String,
+    pub port: Option<u16>,
+    pub username: String,
+    pub password: Option<String>,
+    pub private_key: Option<String>,
+    pub passphrase: Option<String>,
+    pub use_agent: Option<bool>,
+    pub use_keyboard_interactive: Option<bool>,
+}
+
 enum SshChannelCmd {
     Write(Vec<u8>),
     Resize { cols: u32, rows: u32 },
@@ -16,29 +55,595 @@ enum SshChannelCmd {
         command: String,
         reply_tx: mpsc::Sender<Result<String, String>>,
     },
+    StartRecording {
+        path: String,
+        reply_tx: mpsc::Sender<Result<(), String>>,
+    },
+    StopRecording {
+        reply_tx: mpsc::Sender<Result<(), String>>,
+    },
+    OpenLocalForward {
+        listen_addr: String,
+        listen_port: u16,
+        remote_host: String,
+        remote_port: u16,
+        reply_tx: mpsc::Sender<Result<String, String>>,
+    },
+    OpenRemoteForward {
+        bind_addr: String,
+        remote_port: u16,
+        target_host: String,
+        target_port: u16,
+        reply_tx: mpsc::Sender<Result<String, String>>,
+    },
+    SftpListDir {
+        path: String,
+        reply_tx: mpsc::Sender<Result<Vec<SftpEntry>, String>>,
+    },
+    SftpReadFile {
+        path: String,
+        request_id: String,
+        reply_tx: mpsc::Sender<Result<(), String>>,
+    },
+    SftpWriteFile {
+        path: String,
+        data: Vec<u8>,
+        request_id: String,
+        reply_tx: mpsc::Sender<Result<(), String>>,
+    },
+    SftpMkdir {
+        path: String,
+        reply_tx: mpsc::Sender<Result<(), String>>,
+    },
+    SftpRemove {
+        path: String,
+        reply_tx: mpsc::Sender<Result<(), String>>,
+    },
+    SftpRename {
+        from: String,
+        to: String,
+        reply_tx: mpsc::Sender<Result<(), String>>,
+    },
+}
+
+/// A single entry returned by `SshService::list_dir`.
+#[derive(Clone, serde::Serialize)]
+pub struct SftpEntry {
+    name: String,
+    size: u64,
+    permissions: u32,
+    mtime: u64,
+    is_dir: bool,
+}
+
+/// Streamed chunk for the `ssh-sftp-data` event emitted while reading a
+/// remote file; `done` marks the final (empty) chunk of the transfer.
+#[derive(Clone, serde::Serialize)]
+struct SftpDataPayload {
+    server_id: String,
+    request_id: String,
+    data: String,
+    bytes_transferred: u64,
+    total: u64,
+    done: bool,
+}
+
+/// Progress payload for the `ssh-sftp-progress` event emitted while writing
+/// a remote file, so large uploads don't look stalled in the UI.
+#[derive(Clone, serde::Serialize)]
+struct SftpProgressPayload {
+    server_id: String,
+    request_id: String,
+    bytes_transferred: u64,
+    total: u64,
+}
+
+/// Status payload for the `ssh-forward-status` socket event emitted when a
+/// local/remote forward opens, closes, or fails to set up its listener.
+/// A failure on a single forwarded connection is only logged.
+#[derive(Clone, serde::Serialize)]
+struct SshForwardStatusPayload {
+    server_id: String,
+    forward_id: String,
+    status: String,
+    message: Option<String>,
+}
+
+/// A single keyboard-interactive prompt, mirroring `ssh2::Prompt`.
+#[derive(Clone, serde::Serialize)]
+struct SshAuthPromptItem {
+    text: String,
+    echo: bool,
+}
+
+/// Payload for the `ssh-auth-prompt` event; the frontend must answer via
+/// `SshService::respond_auth_prompt(request_id, answers)`.
+#[derive(Clone, serde::Serialize)]
+struct SshAuthPromptPayload {
+    server_id: String,
+    request_id: String,
+    instructions: String,
+    prompts: Vec<SshAuthPromptItem>,
+}
+
+/// Payload for the `ssh-host-key-unknown` event; the frontend must answer
+/// via `SshService::respond_host_key(request_id, accept)`.
+#[derive(Clone, serde::Serialize)]
+struct SshHostKeyUnknownPayload {
+    server_id: String,
+    request_id: String,
+    fingerprint: String,
+}
+
+/// How long a blocked auth prompt or host-key decision waits for the
+/// frontend to answer before the connection attempt gives up.
+const AUTH_PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Forwards libssh2 keyboard-interactive prompts to the frontend over
+/// `ssh-auth-prompt` and blocks until `respond_auth_prompt` answers them.
+struct PromptHandler {
+    socket: SocketRef,
+    server_id: String,
+    request_id: String,
+    pending: Arc<Mutex<HashMap<String, mpsc::Sender<Vec<String>>>>>,
+}
+
+impl ssh2::KeyboardInteractivePrompt for PromptHandler {
+    fn prompt<'a>(
+        &mut self,
+        _username: &str,
+        instructions: &str,
+        prompts: &[ssh2::Prompt<'a>],
+    ) -> Vec<String> {
+        let (tx, rx) = mpsc::channel();
+        self.pending.lock().unwrap().insert(self.request_id.clone(), tx);
+
+        let _ = self.socket.emit(
+            "ssh-auth-prompt",
+            &SshAuthPromptPayload {
+                server_id: self.server_id.clone(),
+                request_id: self.request_id.clone(),
+                instructions: instructions.to_string(),
+                prompts: prompts
+                    .iter()
+                    .map(|p| SshAuthPromptItem {
+                        text: p.text.to_string(),
+                        echo: p.echo,
+                    })
+                    .collect(),
+            },
+        );
+
+        let answers = rx.recv_timeout(AUTH_PROMPT_TIMEOUT).unwrap_or_default();
+        self.pending.lock().unwrap().remove(&self.request_id);
+        answers
+    }
+}
+
+/// Writes a terminal session to disk as an asciicast v2 cast, one JSON
+/// event per line so a crash still leaves a valid, replayable partial file.
+struct TerminalRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl TerminalRecorder {
+    fn new(path: &str, cols: u32, rows: u32) -> std::io::Result<Self> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        let header = json!({
+            "version": 2,
+            "width": cols,
+            "height": rows,
+            "timestamp": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            "env": { "TERM": "xterm-256color" },
+        });
+        writeln!(writer, "{}", header)?;
+        writer.flush()?;
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    fn write_event(&mut self, kind: &str, data: &str) -> std::io::Result<()> {
+        let event = json!([self.start.elapsed().as_secs_f64(), kind, data]);
+        writeln!(self.writer, "{}", event)?;
+        self.writer.flush()
+    }
+
+    fn record_output(&mut self, data: &str) {
+        let _ = self.write_event("o", data);
+    }
+
+    fn record_input(&mut self, data: &str) {
+        let _ = self.write_event("i", data);
+    }
+
+    fn record_resize(&mut self, cols: u32, rows: u32) {
+        let _ = self.write_event("r", &format!("{}x{}", cols, rows));
+    }
 }
 
 struct SshSession {
     session: Arc<Mutex<Session>>,
     tx: mpsc::Sender<SshChannelCmd>,
+    forwards: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    /// Socket currently attached to this session, if any; `None` while the
+    /// session is orphaned (e.g. the websocket dropped or the app reloaded).
+    attached: Arc<Mutex<Option<SocketRef>>>,
+    /// Ring of recent terminal output replayed to a socket on reattach.
+    buffer: Arc<Mutex<VecDeque<u8>>>,
+    last_active: Arc<Mutex<Instant>>,
+}
+
+/// Emits to whichever socket is currently attached to a session, if any.
+/// A detached session simply drops the event instead of erroring.
+fn emit_to_attached<T: serde::Serialize>(
+    attached: &Arc<Mutex<Option<SocketRef>>>,
+    event: &str,
+    payload: &T,
+) {
+    if let Some(socket) = attached.lock().unwrap().as_ref() {
+        let _ = socket.emit(event, payload);
+    }
+}
+
+fn push_to_buffer(buffer: &Arc<Mutex<VecDeque<u8>>>, data: &[u8]) {
+    let mut buffer = buffer.lock().unwrap();
+    buffer.extend(data.iter().copied());
+    while buffer.len() > OUTPUT_BUFFER_CAP {
+        buffer.pop_front();
+    }
+}
+
+/// Stamped onto a socket's extensions by the connection layer's auth
+/// middleware once the handshake is verified. Every session-scoped method
+/// below keys off this instead of a `user_id` forwarded in the RPC payload,
+/// since the latter can be set to anything the client likes.
+pub struct AuthenticatedUser(pub String);
+
+/// Reads the user id the auth middleware verified for this socket. A socket
+/// with no stamp (never authenticated, or stamped by something other than
+/// the auth middleware) is rejected rather than trusted.
+fn require_authenticated_user(socket: &SocketRef) -> Result<String, String> {
+    socket
+        .extensions
+        .get::<AuthenticatedUser>()
+        .map(|u| u.0.clone())
+        .ok_or_else(|| "Socket is not authenticated".to_string())
+}
+
+/// Pumps bytes between a local TCP socket and an SSH forwarding channel
+/// until either side closes or `stop` is raised.
+fn pump_tcp_channel(mut client: TcpStream, mut channel: ssh2::Channel, stop: Arc<AtomicBool>) {
+    let _ = client.set_read_timeout(Some(std::time::Duration::from_millis(100)));
+    let mut buf = [0u8; 8192];
+    while !stop.load(Ordering::Relaxed) {
+        let mut made_progress = false;
+
+        match client.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                if channel.write_all(&buf[..n]).is_err() {
+                    break;
+                }
+                let _ = channel.flush();
+                made_progress = true;
+            }
+            Err(e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(_) => break,
+        }
+
+        match channel.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                if client.write_all(&buf[..n]).is_err() {
+                    break;
+                }
+                made_progress = true;
+            }
+            Err(e)
+                if e.kind() == std::io::ErrorKind::WouldBlock
+                    || e.kind() == std::io::ErrorKind::TimedOut => {}
+            Err(_) => break,
+        }
+
+        if !made_progress {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+    let _ = channel.send_eof();
+    let _ = channel.close();
 }
 
+fn spawn_local_forward(
+    session: Arc<Mutex<Session>>,
+    attached: Arc<Mutex<Option<SocketRef>>>,
+    forwards: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    server_id: String,
+    forward_id: String,
+    listen_addr: String,
+    listen_port: u16,
+    remote_host: String,
+    remote_port: u16,
+    stop: Arc<AtomicBool>,
+) {
+    task::spawn_blocking(move || {
+        let listener = match std::net::TcpListener::bind((listen_addr.as_str(), listen_port)) {
+            Ok(l) => l,
+            Err(e) => {
+                forwards.lock().unwrap().remove(&forward_id);
+                emit_to_attached(
+                    &attached,
+                    "ssh-forward-status",
+                    &SshForwardStatusPayload {
+                        server_id,
+                        forward_id,
+                        status: "error".to_string(),
+                        message: Some(e.to_string()),
+                    },
+                );
+                return;
+            }
+        };
+        let _ = listener.set_nonblocking(true);
+        emit_to_attached(
+            &attached,
+            "ssh-forward-status",
+            &SshForwardStatusPayload {
+                server_id: server_id.clone(),
+                forward_id: forward_id.clone(),
+                status: "open".to_string(),
+                message: None,
+            },
+        );
+
+        while !stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((client, _)) => {
+                    let _ = client.set_nonblocking(false);
+                    let session = session.clone();
+                    let remote_host = remote_host.clone();
+                    let stop = stop.clone();
+                    task::spawn_blocking(move || {
+                        let channel = {
+                            let sess = session.lock().unwrap();
+                            sess.channel_direct_tcpip(&remote_host, remote_port, None)
+                        };
+                        match channel {
+                            Ok(channel) => pump_tcp_channel(client, channel, stop),
+                            Err(e) => error!("direct-tcpip channel failed: {}", e),
+                        }
+                    });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                }
+                Err(e) => {
+                    error!("local forward listener error: {}", e);
+                    break;
+                }
+            }
+        }
+
+        forwards.lock().unwrap().remove(&forward_id);
+        emit_to_attached(
+            &attached,
+            "ssh-forward-status",
+            &SshForwardStatusPayload {
+                server_id,
+                forward_id,
+                status: "closed".to_string(),
+                message: None,
+            },
+        );
+    });
+}
+
+fn spawn_remote_forward(
+    session: Arc<Mutex<Session>>,
+    attached: Arc<Mutex<Option<SocketRef>>>,
+    forwards: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    server_id: String,
+    forward_id: String,
+    bind_addr: String,
+    remote_port: u16,
+    target_host: String,
+    target_port: u16,
+    stop: Arc<AtomicBool>,
+) {
+    task::spawn_blocking(move || {
+        let mut listener = {
+            let sess = session.lock().unwrap();
+            match sess.channel_forward_listen(remote_port as i32, Some(&bind_addr), None) {
+                Ok((listener, _bound_port)) => listener,
+                Err(e) => {
+                    forwards.lock().unwrap().remove(&forward_id);
+                    emit_to_attached(
+                        &attached,
+                        "ssh-forward-status",
+                        &SshForwardStatusPayload {
+                            server_id,
+                            forward_id,
+                            status: "error".to_string(),
+                            message: Some(e.to_string()),
+                        },
+                    );
+                    return;
+                }
+            }
+        };
+
+        emit_to_attached(
+            &attached,
+            "ssh-forward-status",
+            &SshForwardStatusPayload {
+                server_id: server_id.clone(),
+                forward_id: forward_id.clone(),
+                status: "open".to_string(),
+                message: None,
+            },
+        );
+
+        while !stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok(channel) => {
+                    let target_host = target_host.clone();
+                    let stop = stop.clone();
+                    task::spawn_blocking(move || {
+                        match TcpStream::connect((target_host.as_str(), target_port)) {
+                            Ok(tcp) => pump_tcp_channel(tcp, channel, stop),
+                            Err(e) => error!("remote forward target connect failed: {}", e),
+                        }
+                    });
+                }
+                Err(e)
+                    if e.kind() == std::io::ErrorKind::WouldBlock
+                        || e.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(e) => {
+                    error!("remote forward listener error: {}", e);
+                    break;
+                }
+            }
+        }
+
+        forwards.lock().unwrap().remove(&forward_id);
+        emit_to_attached(
+            &attached,
+            "ssh-forward-status",
+            &SshForwardStatusPayload {
+                server_id,
+                forward_id,
+                status: "closed".to_string(),
+                message: None,
+            },
+        );
+    });
+}
+
+/// Owns SSH sessions keyed by a stable `user_id:server_id` pair rather than
+/// by socket id, so a flaky websocket or app reload doesn't tear down live
+/// shells underneath the user. A background sweep reaps sessions that have
+/// sat unattached for longer than `IDLE_TIMEOUT`.
 pub struct SshService {
     sessions: Arc<Mutex<HashMap<String, SshSession>>>,
+    pending_prompts: Arc<Mutex<HashMap<String, mpsc::Sender<Vec<String>>>>>,
+    pending_host_keys: Arc<Mutex<HashMap<String, mpsc::Sender<bool>>>>,
+    /// Source of unique ids for in-flight `connect` attempts, so two
+    /// concurrent attempts for the same `user_id:server_id` don't share a
+    /// `pending_prompts`/`pending_host_keys` entry.
+    next_attempt_id: AtomicU64,
 }
 
 impl SshService {
     pub fn new() -> Self {
+        let sessions = Arc::new(Mutex::new(HashMap::new()));
+        Self::spawn_idle_reaper(sessions.clone());
         Self {
-            sessions: Arc::new(Mutex::new(HashMap::new())),
+            sessions,
+            pending_prompts: Arc::new(Mutex::new(HashMap::new())),
+            pending_host_keys: Arc::new(Mutex::new(HashMap::new())),
+            next_attempt_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Answers a pending `ssh-auth-prompt` with the user's keyboard-interactive responses.
+    pub fn respond_auth_prompt(&self, request_id: &str, answers: Vec<String>) {
+        if let Some(tx) = self.pending_prompts.lock().unwrap().remove(request_id) {
+            let _ = tx.send(answers);
+        }
+    }
+
+    /// Answers a pending `ssh-host-key-unknown` decision.
+    pub fn respond_host_key(&self, request_id: &str, accept: bool) {
+        if let Some(tx) = self.pending_host_keys.lock().unwrap().remove(request_id) {
+            let _ = tx.send(accept);
         }
     }
 
+    fn spawn_idle_reaper(sessions: Arc<Mutex<HashMap<String, SshSession>>>) {
+        task::spawn(async move {
+            loop {
+                tokio::time::sleep(IDLE_SWEEP_INTERVAL).await;
+                let expired: Vec<String> = {
+                    let sessions = sessions.lock().unwrap();
+                    sessions
+                        .iter()
+                        .filter(|(_, s)| {
+                            s.attached.lock().unwrap().is_none()
+                                && s.last_active.lock().unwrap().elapsed() > IDLE_TIMEOUT
+                        })
+                        .map(|(key, _)| key.clone())
+                        .collect()
+                };
+                for key in expired {
+                    if let Some(session) = sessions.lock().unwrap().remove(&key) {
+                        info!("Reaping idle session {}", key);
+                        for stop in session.forwards.lock().unwrap().values() {
+                            stop.store(true, Ordering::Relaxed);
+                        }
+                        let _ = session.tx.send(SshChannelCmd::Disconnect);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Re-attaches a socket to an already-running session, replaying its
+    /// buffered output so the terminal is restored before streaming resumes.
+    pub fn reattach(&self, socket: SocketRef, server_id: &str) -> Result<(), String> {
+        let user_id = require_authenticated_user(&socket)?;
+        let key = format!("{}:{}", user_id, server_id);
+        let sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(&key).ok_or("Session not found")?;
+
+        let replay = {
+            let mut buffer = session.buffer.lock().unwrap();
+            String::from_utf8_lossy(buffer.make_contiguous()).to_string()
+        };
+        if !replay.is_empty() {
+            let _ = socket.emit(
+                "ssh-data",
+                &SshDataPayload {
+                    server_id: server_id.to_string(),
+                    data: replay,
+                },
+            );
+        }
+
+        *session.attached.lock().unwrap() = Some(socket);
+        *session.last_active.lock().unwrap() = Instant::now();
+        Ok(())
+    }
+
     pub fn connect(&self, socket: SocketRef, config: SshConnectionConfig) {
-        let session_key = format!("{}:{}", socket.id, config.server_id);
+        let user_id = match require_authenticated_user(&socket) {
+            Ok(id) => id,
+            Err(e) => {
+                let _ = socket.emit(
+                    "ssh-error",
+                    &SshErrorPayload {
+                        server_id: config.server_id.clone(),
+                        message: e,
+                    },
+                );
+                return;
+            }
+        };
+        let session_key = format!("{}:{}", user_id, config.server_id);
+        let attempt_id = self.next_attempt_id.fetch_add(1, Ordering::Relaxed);
+        let request_id = format!("{}:{}", session_key, attempt_id);
         let sessions = self.sessions.clone();
+        let pending_prompts = self.pending_prompts.clone();
+        let pending_host_keys = self.pending_host_keys.clone();
 
-        self.disconnect(&socket.id.to_string(), &config.server_id);
+        self.disconnect(&socket, &config.server_id);
 
         let server_id = config.server_id.clone();
         let ip = config.ip.clone();
@@ -98,7 +703,118 @@ impl SshService {
             }
             info!("SSH handshake done for server {} ({}:{})", server_id, ip, port);
 
-            if let Some(password) = config.password {
+            {
+                let known_hosts_path = dirs_home_known_hosts();
+                let mut known_hosts = match sess.known_hosts() {
+                    Ok(k) => k,
+                    Err(e) => {
+                        let _ = socket.emit(
+                            "ssh-error",
+                            &SshErrorPayload {
+                                server_id,
+                                message: format!("Failed to load known_hosts: {}", e),
+                            },
+                        );
+                        return;
+                    }
+                };
+                let _ = known_hosts.read_file(&known_hosts_path, ssh2::KnownHostFileKind::OpenSSH);
+
+                let (key, key_type) = match sess.host_key() {
+                    Some(k) => k,
+                    None => {
+                        let _ = socket.emit(
+                            "ssh-error",
+                            &SshErrorPayload {
+                                server_id,
+                                message: "Server did not present a host key".to_string(),
+                            },
+                        );
+                        return;
+                    }
+                };
+
+                match known_hosts.check(&ip, key) {
+                    ssh2::CheckResult::Match => {}
+                    ssh2::CheckResult::Mismatch => {
+                        error!("Host key mismatch for {} ({})", server_id, ip);
+                        let _ = socket.emit(
+                            "ssh-error",
+                            &SshErrorPayload {
+                                server_id,
+                                message: format!(
+                                    "Host key for {} has changed; refusing to connect",
+                                    ip
+                                ),
+                            },
+                        );
+                        return;
+                    }
+                    ssh2::CheckResult::Failure => {
+                        let _ = socket.emit(
+                            "ssh-error",
+                            &SshErrorPayload {
+                                server_id,
+                                message: "Failed to check host key against known_hosts".to_string(),
+                            },
+                        );
+                        return;
+                    }
+                    ssh2::CheckResult::NotFound => {
+                        let fingerprint = sess
+                            .host_key_hash(ssh2::HashType::Sha256)
+                            .map(|h| base64::engine::general_purpose::STANDARD.encode(h))
+                            .unwrap_or_default();
+
+                        let (tx, rx) = mpsc::channel();
+                        pending_host_keys
+                            .lock()
+                            .unwrap()
+                            .insert(request_id.clone(), tx);
+
+                        let _ = socket.emit(
+                            "ssh-host-key-unknown",
+                            &SshHostKeyUnknownPayload {
+                                server_id: server_id.clone(),
+                                request_id: request_id.clone(),
+                                fingerprint,
+                            },
+                        );
+
+                        let accept = rx.recv_timeout(AUTH_PROMPT_TIMEOUT).unwrap_or(false);
+                        pending_host_keys.lock().unwrap().remove(&request_id);
+
+                        if !accept {
+                            let _ = socket.emit(
+                                "ssh-error",
+                                &SshErrorPayload {
+                                    server_id,
+                                    message: "Host key rejected by user".to_string(),
+                                },
+                            );
+                            return;
+                        }
+
+                        let _ = known_hosts.add(&ip, key, "added by aissh", key_type.into());
+                        let _ = known_hosts.write_file(&known_hosts_path, ssh2::KnownHostFileKind::OpenSSH);
+                    }
+                }
+            }
+
+            if config.use_agent.unwrap_or(false) {
+                info!("Attempting agent auth for user: {} on server: {}", config.username, server_id);
+                if let Err(e) = sess.userauth_agent(&config.username) {
+                    error!("SSH agent auth failed for {}: {}", config.username, e);
+                    let _ = socket.emit(
+                        "ssh-error",
+                        &SshErrorPayload {
+                            server_id,
+                            message: format!("Agent authentication failed: {}", e),
+                        },
+                    );
+                    return;
+                }
+            } else if let Some(password) = config.password {
                 info!("Attempting password auth for user: {} on server: {}", config.username, server_id);
                 if let Err(e) = sess.userauth_password(&config.username, &password) {
                     error!("SSH Auth failed for {}: {}", config.username, e);
@@ -112,7 +828,9 @@ impl SshService {
                     return;
                 }
             } else if let Some(pk) = config.private_key {
-                if let Err(e) = sess.userauth_pubkey_memory(&config.username, None, &pk, None) {
+                if let Err(e) =
+                    sess.userauth_pubkey_memory(&config.username, None, &pk, config.passphrase.as_deref())
+                {
                     let _ = socket.emit(
                         "ssh-error",
                         &SshErrorPayload {
@@ -122,6 +840,26 @@ impl SshService {
                     );
                     return;
                 }
+            } else if config.use_keyboard_interactive.unwrap_or(false) {
+                info!("Attempting keyboard-interactive auth for user: {} on server: {}", config.username, server_id);
+                let mut prompt_handler = PromptHandler {
+                    socket: socket.clone(),
+                    server_id: server_id.clone(),
+                    request_id: request_id.clone(),
+                    pending: pending_prompts.clone(),
+                };
+                if let Err(e) =
+                    sess.userauth_keyboard_interactive(&config.username, &mut prompt_handler)
+                {
+                    let _ = socket.emit(
+                        "ssh-error",
+                        &SshErrorPayload {
+                            server_id,
+                            message: format!("Keyboard-interactive authentication failed: {}", e),
+                        },
+                    );
+                    return;
+                }
             } else {
                 let _ = socket.emit(
                     "ssh-error",
@@ -186,9 +924,17 @@ impl SshService {
 
             let (tx, rx) = mpsc::channel::<SshChannelCmd>();
             let session = Arc::new(Mutex::new(sess));
+            let forwards = Arc::new(Mutex::new(HashMap::new()));
+            let attached = Arc::new(Mutex::new(Some(socket)));
+            let buffer_ring = Arc::new(Mutex::new(VecDeque::new()));
+            let last_active = Arc::new(Mutex::new(Instant::now()));
             let ssh_session = SshSession {
                 session: session.clone(),
                 tx,
+                forwards: forwards.clone(),
+                attached: attached.clone(),
+                buffer: buffer_ring.clone(),
+                last_active: last_active.clone(),
             };
 
             sessions
@@ -196,29 +942,312 @@ impl SshService {
                 .unwrap()
                 .insert(session_key.clone(), ssh_session);
 
-            let socket_inner = socket.clone();
+            let attached_inner = attached.clone();
             let server_id_inner = server_id.clone();
             let session_inner = session.clone(); // Keep session alive
             let sessions_inner = sessions.clone();
             let session_key_inner = session_key.clone();
+            let forwards_inner = forwards.clone();
+            let buffer_inner = buffer_ring.clone();
+            let last_active_inner = last_active.clone();
 
             task::spawn_blocking(move || {
                 let mut buffer = [0u8; 8192];
                 let mut disconnect_requested = false;
+                let mut cols = 80u32;
+                let mut rows = 24u32;
+                let mut recorder: Option<TerminalRecorder> = None;
+                let mut forward_seq: u64 = 0;
                 'outer: loop {
                     loop {
                         match rx.try_recv() {
                             Ok(SshChannelCmd::Write(data)) => {
                                 let _ = channel.write_all(&data);
                                 let _ = channel.flush();
+                                if let Some(rec) = recorder.as_mut() {
+                                    rec.record_input(&String::from_utf8_lossy(&data));
+                                }
+                                *last_active_inner.lock().unwrap() = Instant::now();
                             }
-                            Ok(SshChannelCmd::Resize { cols, rows }) => {
-                                let _ = channel.request_pty_size(cols, rows, None, None);
+                            Ok(SshChannelCmd::Resize { cols: c, rows: r }) => {
+                                let _ = channel.request_pty_size(c, r, None, None);
+                                cols = c;
+                                rows = r;
+                                if let Some(rec) = recorder.as_mut() {
+                                    rec.record_resize(c, r);
+                                }
                             }
                             Ok(SshChannelCmd::Disconnect) => {
                                 disconnect_requested = true;
                                 break 'outer;
                             }
+                            Ok(SshChannelCmd::StartRecording { path, reply_tx }) => {
+                                let result = TerminalRecorder::new(&path, cols, rows)
+                                    .map(|r| recorder = Some(r))
+                                    .map_err(|e| e.to_string());
+                                let _ = reply_tx.send(result);
+                            }
+                            Ok(SshChannelCmd::StopRecording { reply_tx }) => {
+                                recorder = None;
+                                let _ = reply_tx.send(Ok(()));
+                            }
+                            Ok(SshChannelCmd::OpenLocalForward {
+                                listen_addr,
+                                listen_port,
+                                remote_host,
+                                remote_port,
+                                reply_tx,
+                            }) => {
+                                forward_seq += 1;
+                                let forward_id = format!(
+                                    "local:{}:{}:{}->{}:{}",
+                                    forward_seq, listen_addr, listen_port, remote_host, remote_port
+                                );
+                                let stop = Arc::new(AtomicBool::new(false));
+                                forwards_inner
+                                    .lock()
+                                    .unwrap()
+                                    .insert(forward_id.clone(), stop.clone());
+                                spawn_local_forward(
+                                    session_inner.clone(),
+                                    attached_inner.clone(),
+                                    forwards_inner.clone(),
+                                    server_id_inner.clone(),
+                                    forward_id.clone(),
+                                    listen_addr,
+                                    listen_port,
+                                    remote_host,
+                                    remote_port,
+                                    stop,
+                                );
+                                let _ = reply_tx.send(Ok(forward_id));
+                            }
+                            Ok(SshChannelCmd::OpenRemoteForward {
+                                bind_addr,
+                                remote_port,
+                                target_host,
+                                target_port,
+                                reply_tx,
+                            }) => {
+                                forward_seq += 1;
+                                let forward_id = format!(
+                                    "remote:{}:{}:{}->{}:{}",
+                                    forward_seq, bind_addr, remote_port, target_host, target_port
+                                );
+                                let stop = Arc::new(AtomicBool::new(false));
+                                forwards_inner
+                                    .lock()
+                                    .unwrap()
+                                    .insert(forward_id.clone(), stop.clone());
+                                spawn_remote_forward(
+                                    session_inner.clone(),
+                                    attached_inner.clone(),
+                                    forwards_inner.clone(),
+                                    server_id_inner.clone(),
+                                    forward_id.clone(),
+                                    bind_addr,
+                                    remote_port,
+                                    target_host,
+                                    target_port,
+                                    stop,
+                                );
+                                let _ = reply_tx.send(Ok(forward_id));
+                            }
+                            Ok(SshChannelCmd::SftpListDir { path, reply_tx }) => {
+                                let result = (|| {
+                                    let sess = session_inner.lock().unwrap();
+                                    let prev_timeout = sess.timeout();
+                                    sess.set_timeout(60000); // SFTP 往返可能超过 shell 轮询的 100ms 超时
+
+                                    let res = (|| {
+                                        let sftp = sess.sftp().map_err(|e| e.to_string())?;
+                                        let entries = sftp
+                                            .readdir(std::path::Path::new(&path))
+                                            .map_err(|e| e.to_string())?;
+                                        Ok(entries
+                                            .into_iter()
+                                            .map(|(entry_path, stat)| SftpEntry {
+                                                name: entry_path
+                                                    .file_name()
+                                                    .map(|n| n.to_string_lossy().to_string())
+                                                    .unwrap_or_default(),
+                                                size: stat.size.unwrap_or(0),
+                                                permissions: stat.perm.unwrap_or(0),
+                                                mtime: stat.mtime.unwrap_or(0),
+                                                is_dir: stat.is_dir(),
+                                            })
+                                            .collect::<Vec<_>>())
+                                    })();
+
+                                    sess.set_timeout(prev_timeout);
+                                    res
+                                })();
+                                let _ = reply_tx.send(result);
+                            }
+                            Ok(SshChannelCmd::SftpReadFile {
+                                path,
+                                request_id,
+                                reply_tx,
+                            }) => {
+                                let result = (|| {
+                                    let sess = session_inner.lock().unwrap();
+                                    let prev_timeout = sess.timeout();
+                                    sess.set_timeout(60000); // SFTP 往返可能超过 shell 轮询的 100ms 超时
+
+                                    let res = (|| {
+                                        let sftp = sess.sftp().map_err(|e| e.to_string())?;
+                                        let mut file = sftp
+                                            .open(std::path::Path::new(&path))
+                                            .map_err(|e| e.to_string())?;
+                                        let total = file
+                                            .stat()
+                                            .map(|s| s.size.unwrap_or(0))
+                                            .unwrap_or(0);
+
+                                        let mut transferred = 0u64;
+                                        let mut buf = [0u8; 32 * 1024];
+                                        loop {
+                                            let n = file.read(&mut buf).map_err(|e| e.to_string())?;
+                                            if n == 0 {
+                                                break;
+                                            }
+                                            transferred += n as u64;
+                                            emit_to_attached(
+                                                &attached_inner,
+                                                "ssh-sftp-data",
+                                                &SftpDataPayload {
+                                                    server_id: server_id_inner.clone(),
+                                                    request_id: request_id.clone(),
+                                                    data: base64::engine::general_purpose::STANDARD
+                                                        .encode(&buf[..n]),
+                                                    bytes_transferred: transferred,
+                                                    total,
+                                                    done: false,
+                                                },
+                                            );
+                                        }
+
+                                        emit_to_attached(
+                                            &attached_inner,
+                                            "ssh-sftp-data",
+                                            &SftpDataPayload {
+                                                server_id: server_id_inner.clone(),
+                                                request_id: request_id.clone(),
+                                                data: String::new(),
+                                                bytes_transferred: transferred,
+                                                total,
+                                                done: true,
+                                            },
+                                        );
+                                        Ok(())
+                                    })();
+
+                                    sess.set_timeout(prev_timeout);
+                                    res
+                                })();
+                                let _ = reply_tx.send(result);
+                            }
+                            Ok(SshChannelCmd::SftpWriteFile {
+                                path,
+                                data,
+                                request_id,
+                                reply_tx,
+                            }) => {
+                                let result = (|| {
+                                    let sess = session_inner.lock().unwrap();
+                                    let prev_timeout = sess.timeout();
+                                    sess.set_timeout(60000); // SFTP 往返可能超过 shell 轮询的 100ms 超时
+
+                                    let res = (|| {
+                                        let sftp = sess.sftp().map_err(|e| e.to_string())?;
+                                        let mut file = sftp
+                                            .create(std::path::Path::new(&path))
+                                            .map_err(|e| e.to_string())?;
+                                        let total = data.len() as u64;
+                                        let mut transferred = 0u64;
+                                        for chunk in data.chunks(32 * 1024) {
+                                            file.write_all(chunk).map_err(|e| e.to_string())?;
+                                            transferred += chunk.len() as u64;
+                                            emit_to_attached(
+                                                &attached_inner,
+                                                "ssh-sftp-progress",
+                                                &SftpProgressPayload {
+                                                    server_id: server_id_inner.clone(),
+                                                    request_id: request_id.clone(),
+                                                    bytes_transferred: transferred,
+                                                    total,
+                                                },
+                                            );
+                                        }
+                                        Ok(())
+                                    })();
+
+                                    sess.set_timeout(prev_timeout);
+                                    res
+                                })();
+                                let _ = reply_tx.send(result);
+                            }
+                            Ok(SshChannelCmd::SftpMkdir { path, reply_tx }) => {
+                                let result = (|| {
+                                    let sess = session_inner.lock().unwrap();
+                                    let prev_timeout = sess.timeout();
+                                    sess.set_timeout(60000); // SFTP 往返可能超过 shell 轮询的 100ms 超时
+
+                                    let res = sess
+                                        .sftp()
+                                        .map_err(|e| e.to_string())
+                                        .and_then(|sftp| {
+                                            sftp.mkdir(std::path::Path::new(&path), 0o755)
+                                                .map_err(|e| e.to_string())
+                                        });
+
+                                    sess.set_timeout(prev_timeout);
+                                    res
+                                })();
+                                let _ = reply_tx.send(result);
+                            }
+                            Ok(SshChannelCmd::SftpRemove { path, reply_tx }) => {
+                                let result = (|| {
+                                    let sess = session_inner.lock().unwrap();
+                                    let prev_timeout = sess.timeout();
+                                    sess.set_timeout(60000); // SFTP 往返可能超过 shell 轮询的 100ms 超时
+
+                                    let res = sess
+                                        .sftp()
+                                        .map_err(|e| e.to_string())
+                                        .and_then(|sftp| {
+                                            sftp.unlink(std::path::Path::new(&path))
+                                                .map_err(|e| e.to_string())
+                                        });
+
+                                    sess.set_timeout(prev_timeout);
+                                    res
+                                })();
+                                let _ = reply_tx.send(result);
+                            }
+                            Ok(SshChannelCmd::SftpRename { from, to, reply_tx }) => {
+                                let result = (|| {
+                                    let sess = session_inner.lock().unwrap();
+                                    let prev_timeout = sess.timeout();
+                                    sess.set_timeout(60000); // SFTP 往返可能超过 shell 轮询的 100ms 超时
+
+                                    let res = sess
+                                        .sftp()
+                                        .map_err(|e| e.to_string())
+                                        .and_then(|sftp| {
+                                            sftp.rename(
+                                                std::path::Path::new(&from),
+                                                std::path::Path::new(&to),
+                                                None,
+                                            )
+                                            .map_err(|e| e.to_string())
+                                        });
+
+                                    sess.set_timeout(prev_timeout);
+                                    res
+                                })();
+                                let _ = reply_tx.send(result);
+                            }
                             Ok(SshChannelCmd::Exec { command, reply_tx }) => {
                                 let result = (|| {
                                     let sess = session_inner.lock().unwrap();
@@ -253,7 +1282,12 @@ impl SshService {
                         }
                         Ok(n) => {
                             let data = String::from_utf8_lossy(&buffer[..n]).to_string();
-                            let _ = socket_inner.emit(
+                            if let Some(rec) = recorder.as_mut() {
+                                rec.record_output(&data);
+                            }
+                            push_to_buffer(&buffer_inner, &buffer[..n]);
+                            emit_to_attached(
+                                &attached_inner,
                                 "ssh-data",
                                 &SshDataPayload {
                                     server_id: server_id_inner.clone(),
@@ -275,6 +1309,10 @@ impl SshService {
                 let _ = channel.send_eof();
                 let _ = channel.close();
 
+                for stop in forwards_inner.lock().unwrap().values() {
+                    stop.store(true, Ordering::Relaxed);
+                }
+
                 if let Ok(sess) = session_inner.lock() {
                     let _ = sess.disconnect(None, "Disconnected", None);
                 }
@@ -283,7 +1321,8 @@ impl SshService {
                 info!("Cleaning up session {}", session_key_inner);
                 sessions_inner.lock().unwrap().remove(&session_key_inner);
 
-                let _ = socket_inner.emit(
+                emit_to_attached(
+                    &attached_inner,
                     "ssh-status",
                     &SshStatusPayload {
                         server_id: server_id_inner,
@@ -295,8 +1334,12 @@ impl SshService {
         });
     }
 
-    pub fn write(&self, socket_id: &str, server_id: &str, data: &str) {
-        let key = format!("{}:{}", socket_id, server_id);
+    pub fn write(&self, socket: &SocketRef, server_id: &str, data: &str) {
+        let user_id = match require_authenticated_user(socket) {
+            Ok(id) => id,
+            Err(_) => return,
+        };
+        let key = format!("{}:{}", user_id, server_id);
         if let Some(session) = self.sessions.lock().unwrap().get(&key) {
             let _ = session
                 .tx
@@ -304,46 +1347,60 @@ impl SshService {
         }
     }
 
-    pub fn resize(&self, socket_id: &str, server_id: &str, cols: u32, rows: u32) {
-        let key = format!("{}:{}", socket_id, server_id);
+    pub fn resize(&self, socket: &SocketRef, server_id: &str, cols: u32, rows: u32) {
+        let user_id = match require_authenticated_user(socket) {
+            Ok(id) => id,
+            Err(_) => return,
+        };
+        let key = format!("{}:{}", user_id, server_id);
         if let Some(session) = self.sessions.lock().unwrap().get(&key) {
             let _ = session.tx.send(SshChannelCmd::Resize { cols, rows });
         }
     }
 
-    pub fn disconnect(&self, socket_id: &str, server_id: &str) {
-        let key = format!("{}:{}", socket_id, server_id);
+    pub fn disconnect(&self, socket: &SocketRef, server_id: &str) {
+        let user_id = match require_authenticated_user(socket) {
+            Ok(id) => id,
+            Err(_) => return,
+        };
+        let key = format!("{}:{}", user_id, server_id);
         if let Some(session) = self.sessions.lock().unwrap().remove(&key) {
+            for stop in session.forwards.lock().unwrap().values() {
+                stop.store(true, Ordering::Relaxed);
+            }
             let _ = session.tx.send(SshChannelCmd::Disconnect);
             let sess = session.session.lock().unwrap();
             let _ = sess.disconnect(None, "Disconnected", None);
         }
     }
 
+    /// Detaches a socket from every session it's attached to without
+    /// terminating them; a dropped websocket or app reload no longer kills
+    /// the live shells, they just sit idle until a socket reattaches or the
+    /// idle reaper removes them after `IDLE_TIMEOUT`.
     pub fn disconnect_all(&self, socket_id: &str) {
-        let server_ids: Vec<String> = {
-            let sessions = self.sessions.lock().unwrap();
-            sessions
-                .keys()
-                .filter_map(|k| {
-                    k.strip_prefix(&format!("{}:", socket_id))
-                        .map(|v| v.to_string())
-                })
-                .collect()
-        };
-
-        for server_id in server_ids {
-            self.disconnect(socket_id, &server_id);
+        for session in self.sessions.lock().unwrap().values() {
+            let mut attached = session.attached.lock().unwrap();
+            if attached.as_ref().map(|s| s.id.to_string()).as_deref() == Some(socket_id) {
+                *attached = None;
+                *session.last_active.lock().unwrap() = Instant::now();
+            }
         }
     }
 
-    pub async fn exec(
+    /// Looks up the session's command channel and round-trips a single
+    /// `SshChannelCmd` through it, replying on a one-shot `mpsc` channel.
+    /// This is the shared shape behind every public command method below:
+    /// find the session, send a command built around a fresh reply sender,
+    /// then block on the reply off the async runtime.
+    async fn dispatch<T: Send + 'static>(
         &self,
-        socket_id: &str,
+        socket: &SocketRef,
         server_id: &str,
-        command: String,
-    ) -> Result<String, String> {
-        let key = format!("{}:{}", socket_id, server_id);
+        build_cmd: impl FnOnce(mpsc::Sender<Result<T, String>>) -> SshChannelCmd,
+    ) -> Result<T, String> {
+        let user_id = require_authenticated_user(socket)?;
+        let key = format!("{}:{}", user_id, server_id);
         let tx = {
             let sessions = self.sessions.lock().unwrap();
             sessions
@@ -353,15 +1410,170 @@ impl SshService {
         };
 
         let (reply_tx, reply_rx) = mpsc::channel();
-        tx.send(SshChannelCmd::Exec { command, reply_tx })
-            .map_err(|e| e.to_string())?;
+        tx.send(build_cmd(reply_tx)).map_err(|e| e.to_string())?;
 
-        task::spawn_blocking(move || {
-            reply_rx
-                .recv()
-                .map_err(|e| e.to_string())?
+        task::spawn_blocking(move || reply_rx.recv().map_err(|e| e.to_string())?)
+            .await
+            .map_err(|e| e.to_string())?
+    }
+
+    pub async fn exec(
+        &self,
+        socket: &SocketRef,
+        server_id: &str,
+        command: String,
+    ) -> Result<String, String> {
+        self.dispatch(socket, server_id, |reply_tx| SshChannelCmd::Exec {
+            command,
+            reply_tx,
+        })
+        .await
+    }
+
+    pub async fn start_recording(
+        &self,
+        socket: &SocketRef,
+        server_id: &str,
+        path: String,
+    ) -> Result<(), String> {
+        self.dispatch(socket, server_id, |reply_tx| {
+            SshChannelCmd::StartRecording { path, reply_tx }
+        })
+        .await
+    }
+
+    pub async fn stop_recording(&self, socket: &SocketRef, server_id: &str) -> Result<(), String> {
+        self.dispatch(socket, server_id, |reply_tx| {
+            SshChannelCmd::StopRecording { reply_tx }
+        })
+        .await
+    }
+
+    /// Opens a local TCP listener that forwards accepted connections to
+    /// `remote_host:remote_port` over the session's SSH channel. Returns a
+    /// forward id that identifies the listener in `ssh-forward-status` events.
+    pub async fn forward_local(
+        &self,
+        socket: &SocketRef,
+        server_id: &str,
+        listen_addr: String,
+        listen_port: u16,
+        remote_host: String,
+        remote_port: u16,
+    ) -> Result<String, String> {
+        self.dispatch(socket, server_id, |reply_tx| {
+            SshChannelCmd::OpenLocalForward {
+                listen_addr,
+                listen_port,
+                remote_host,
+                remote_port,
+                reply_tx,
+            }
+        })
+        .await
+    }
+
+    /// Asks the remote server to listen on `bind_addr:remote_port` and relays
+    /// each inbound channel to `target_host:target_port` on this machine.
+    pub async fn forward_remote(
+        &self,
+        socket: &SocketRef,
+        server_id: &str,
+        bind_addr: String,
+        remote_port: u16,
+        target_host: String,
+        target_port: u16,
+    ) -> Result<String, String> {
+        self.dispatch(socket, server_id, |reply_tx| {
+            SshChannelCmd::OpenRemoteForward {
+                bind_addr,
+                remote_port,
+                target_host,
+                target_port,
+                reply_tx,
+            }
+        })
+        .await
+    }
+
+    /// Lists a remote directory over SFTP.
+    pub async fn list_dir(
+        &self,
+        socket: &SocketRef,
+        server_id: &str,
+        path: String,
+    ) -> Result<Vec<SftpEntry>, String> {
+        self.dispatch(socket, server_id, |reply_tx| SshChannelCmd::SftpListDir {
+            path,
+            reply_tx,
+        })
+        .await
+    }
+
+    /// Streams a remote file's contents over the `ssh-sftp-data` event as
+    /// base64 chunks tagged with `request_id`, rather than buffering it.
+    pub async fn read_file(
+        &self,
+        socket: &SocketRef,
+        server_id: &str,
+        path: String,
+        request_id: String,
+    ) -> Result<(), String> {
+        self.dispatch(socket, server_id, |reply_tx| SshChannelCmd::SftpReadFile {
+            path,
+            request_id,
+            reply_tx,
+        })
+        .await
+    }
+
+    /// Writes `data` to a remote file, reporting progress over the
+    /// `ssh-sftp-progress` event tagged with `request_id`.
+    pub async fn write_file(
+        &self,
+        socket: &SocketRef,
+        server_id: &str,
+        path: String,
+        data: Vec<u8>,
+        request_id: String,
+    ) -> Result<(), String> {
+        self.dispatch(socket, server_id, |reply_tx| SshChannelCmd::SftpWriteFile {
+            path,
+            data,
+            request_id,
+            reply_tx,
+        })
+        .await
+    }
+
+    pub async fn mkdir(&self, socket: &SocketRef, server_id: &str, path: String) -> Result<(), String> {
+        self.dispatch(socket, server_id, |reply_tx| SshChannelCmd::SftpMkdir {
+            path,
+            reply_tx,
+        })
+        .await
+    }
+
+    pub async fn remove(&self, socket: &SocketRef, server_id: &str, path: String) -> Result<(), String> {
+        self.dispatch(socket, server_id, |reply_tx| SshChannelCmd::SftpRemove {
+            path,
+            reply_tx,
+        })
+        .await
+    }
+
+    pub async fn rename(
+        &self,
+        socket: &SocketRef,
+        server_id: &str,
+        from: String,
+        to: String,
+    ) -> Result<(), String> {
+        self.dispatch(socket, server_id, |reply_tx| SshChannelCmd::SftpRename {
+            from,
+            to,
+            reply_tx,
         })
         .await
-        .map_err(|e| e.to_string())?
     }
 }